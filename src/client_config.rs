@@ -0,0 +1,90 @@
+use crate::retry::RetryPolicy;
+use std::time::Duration;
+
+/// Configuration used to construct a
+/// [`SkySparkClient`](crate::SkySparkClient) via
+/// [`SkySparkClient::with_config`](crate::SkySparkClient::with_config).
+///
+/// Build one with [`ClientConfig::builder`].
+#[derive(Debug, Clone, Default)]
+pub struct ClientConfig {
+    pub(crate) connect_timeout: Option<Duration>,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) http_client: Option<reqwest::Client>,
+    pub(crate) user_agent: Option<String>,
+    pub(crate) retry_policy: RetryPolicy,
+}
+
+impl ClientConfig {
+    /// Returns a builder for constructing a `ClientConfig`.
+    pub fn builder() -> ClientConfigBuilder {
+        ClientConfigBuilder::default()
+    }
+}
+
+/// Builds a [`ClientConfig`].
+#[derive(Debug, Default)]
+pub struct ClientConfigBuilder {
+    connect_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    http_client: Option<reqwest::Client>,
+    user_agent: Option<String>,
+    retry_policy: RetryPolicy,
+}
+
+impl ClientConfigBuilder {
+    /// Sets the TCP connect timeout applied to every request.
+    /// Ignored if [`ClientConfigBuilder::http_client`] is also set,
+    /// since that client's own timeouts are used instead.
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = Some(connect_timeout);
+        self
+    }
+
+    /// Sets the overall request timeout applied to every request.
+    /// Ignored if [`ClientConfigBuilder::http_client`] is also set,
+    /// since that client's own timeouts are used instead.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Supplies a pre-built `reqwest::Client` for the
+    /// `SkySparkClient` to use, instead of building one from the
+    /// other options on this builder. Useful so many `SkySparkClient`s
+    /// across a project can share one connection pool and one source
+    /// of randomness, and so callers can configure TLS options (root
+    /// certificates, minimum TLS version, client certificates, etc.)
+    /// which aren't exposed directly on `ClientConfig`.
+    pub fn http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    /// Sets the `User-Agent` header sent with every request. Ignored
+    /// if [`ClientConfigBuilder::http_client`] is also set.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Sets the policy used to automatically re-authenticate and
+    /// retry a request when the client's SkySpark auth token has
+    /// expired. Defaults to [`RetryPolicy::none`], which never
+    /// retries.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Builds the `ClientConfig`.
+    pub fn build(self) -> ClientConfig {
+        ClientConfig {
+            connect_timeout: self.connect_timeout,
+            timeout: self.timeout,
+            http_client: self.http_client,
+            user_agent: self.user_agent,
+            retry_policy: self.retry_policy,
+        }
+    }
+}
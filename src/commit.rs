@@ -0,0 +1,195 @@
+use crate::haystack_http::{auth_header, op_url};
+use raystack::{Error, Grid};
+use serde_json::Value;
+use std::fmt;
+use url::Url;
+
+/// An error returned by [`SkySparkClient::commit_add`],
+/// [`SkySparkClient::commit_update`], or
+/// [`SkySparkClient::commit_remove`].
+///
+/// [`SkySparkClient::commit_add`]: crate::SkySparkClient::commit_add
+/// [`SkySparkClient::commit_update`]: crate::SkySparkClient::commit_update
+/// [`SkySparkClient::commit_remove`]: crate::SkySparkClient::commit_remove
+#[derive(Debug)]
+pub enum CommitError {
+    /// The commit was rejected because the auth token used to send it
+    /// was missing or had expired. Detected structurally from the
+    /// response's HTTP status rather than guessed at from an error
+    /// message, so [`SkySparkClient::run_with_retry`](crate::SkySparkClient)
+    /// can reliably re-authenticate and retry the commit.
+    Unauthorized,
+    /// The server rejected the commit because one or more records
+    /// had been modified since the `mod` timestamp supplied in the
+    /// request. Re-read the affected records and retry the commit
+    /// with their current `mod` timestamps.
+    Conflict,
+    /// The server rejected the commit for some other reason, and
+    /// responded with a Haystack error grid describing why.
+    Server(Grid),
+    /// Sending the request or parsing the response itself failed
+    /// (a network error, or a malformed response body).
+    Transport(Error),
+}
+
+impl fmt::Display for CommitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommitError::Unauthorized => {
+                write!(f, "commit rejected: missing or expired auth token")
+            }
+            CommitError::Conflict => write!(
+                f,
+                "commit conflict: one or more records were modified concurrently"
+            ),
+            CommitError::Server(grid) => {
+                write!(f, "server rejected commit: {}", grid.to_json_string())
+            }
+            CommitError::Transport(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for CommitError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CommitError::Unauthorized | CommitError::Conflict | CommitError::Server(_) => None,
+            CommitError::Transport(err) => Some(err),
+        }
+    }
+}
+
+impl From<Error> for CommitError {
+    fn from(err: Error) -> Self {
+        CommitError::Transport(err)
+    }
+}
+
+/// The unqualified Fantom/folio error type for a concurrent-edit
+/// conflict, reported in the error grid's `errType` meta tag.
+/// SkySpark reports this as a namespaced qname (for example
+/// `folio::ConcurrentChangeErr`), so this matches on the unqualified
+/// name rather than a full qname in case the namespace varies by
+/// deployment or SkySpark version.
+const CONCURRENT_CHANGE_ERR_TYPE: &str = "ConcurrentChangeErr";
+
+/// Reads the `errType` meta tag out of a raw (not-yet-parsed-as-Grid)
+/// Haystack JSON error response.
+fn err_type(body: &Value) -> Option<&str> {
+    body.pointer("/meta/errType").and_then(Value::as_str)
+}
+
+/// Returns true if `err_type` (a raw or namespaced Fantom error type,
+/// e.g. `folio::ConcurrentChangeErr` or `ConcurrentChangeErr`) names a
+/// concurrent-edit conflict.
+fn is_concurrent_change_err_type(err_type: &str) -> bool {
+    err_type.rsplit("::").next() == Some(CONCURRENT_CHANGE_ERR_TYPE)
+}
+
+/// POSTs a commit grid (already carrying its `commit` meta tag) to
+/// the `commit` op, and maps a non-2xx response onto a [`CommitError`]
+/// using the structured `errType` meta tag rather than matching
+/// against the server's (unstable) human-readable error message.
+pub(crate) async fn post_commit_grid(
+    http_client: &reqwest::Client,
+    project_api_url: &Url,
+    auth_token: &str,
+    request: &Value,
+) -> Result<Grid, CommitError> {
+    let url = op_url(project_api_url, "commit");
+    let response = http_client
+        .post(url)
+        .header(reqwest::header::AUTHORIZATION, auth_header(auth_token))
+        .header(reqwest::header::ACCEPT, "application/json")
+        .json(request)
+        .send()
+        .await
+        .map_err(Error::from)?;
+    let status = response.status();
+    let text = response.text().await.map_err(Error::from)?;
+
+    if !status.is_success() {
+        if status == reqwest::StatusCode::UNAUTHORIZED {
+            return Err(CommitError::Unauthorized);
+        }
+        if let Ok(body) = serde_json::from_str::<Value>(&text) {
+            if err_type(&body).map_or(false, is_concurrent_change_err_type) {
+                return Err(CommitError::Conflict);
+            }
+        }
+        return match Grid::from_json_string(&text) {
+            Ok(grid) => Err(CommitError::Server(grid)),
+            Err(parse_err) => Err(CommitError::Transport(Error::from(parse_err))),
+        };
+    }
+
+    Grid::from_json_string(&text).map_err(Error::from).map_err(CommitError::from)
+}
+
+/// Parses a record grid's raw JSON text and injects a `commit` meta
+/// tag into it, ready to POST to the `commit` op.
+pub(crate) fn commit_request_json(recs_json: &str, commit_kind: &str) -> Value {
+    let mut value: Value =
+        serde_json::from_str(recs_json).expect("Grid::to_json_string should produce valid JSON");
+    if let Value::Object(grid) = &mut value {
+        let meta = grid
+            .entry("meta".to_string())
+            .or_insert_with(|| Value::Object(Default::default()));
+        if let Value::Object(meta) = meta {
+            meta.insert("commit".to_string(), Value::String(commit_kind.to_string()));
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn err_type_reads_errtype_meta_tag() {
+        let body = serde_json::json!({"meta": {"errType": "folio::ConcurrentChangeErr"}});
+        assert_eq!(err_type(&body), Some("folio::ConcurrentChangeErr"));
+    }
+
+    #[test]
+    fn err_type_is_none_when_absent() {
+        let body = serde_json::json!({"meta": {"err": true}});
+        assert_eq!(err_type(&body), None);
+    }
+
+    #[test]
+    fn is_concurrent_change_err_type_matches_namespaced_qname() {
+        assert!(is_concurrent_change_err_type("folio::ConcurrentChangeErr"));
+    }
+
+    #[test]
+    fn is_concurrent_change_err_type_matches_unqualified_name() {
+        assert!(is_concurrent_change_err_type("ConcurrentChangeErr"));
+    }
+
+    #[test]
+    fn is_concurrent_change_err_type_rejects_other_types() {
+        assert!(!is_concurrent_change_err_type("folio::PermissionErr"));
+        assert!(!is_concurrent_change_err_type("sys::Err"));
+    }
+
+    #[test]
+    fn commit_request_json_injects_commit_meta_tag() {
+        let recs = r#"{"_kind":"grid","meta":{"ver":"3.0"},"cols":[{"name":"id"}],"rows":[{"id":"r:a"}]}"#;
+        let request = commit_request_json(recs, "add");
+        assert_eq!(request["meta"]["commit"], Value::String("add".to_string()));
+        assert_eq!(request["meta"]["ver"], Value::String("3.0".to_string()));
+        assert_eq!(request["rows"][0]["id"], Value::String("r:a".to_string()));
+    }
+
+    #[test]
+    fn commit_request_json_creates_meta_if_absent() {
+        let recs = r#"{"_kind":"grid","cols":[{"name":"id"}],"rows":[{"id":"r:a"}]}"#;
+        let request = commit_request_json(recs, "remove");
+        assert_eq!(
+            request["meta"]["commit"],
+            Value::String("remove".to_string())
+        );
+    }
+}
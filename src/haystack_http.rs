@@ -0,0 +1,171 @@
+//! Low-level plumbing shared by every op that this crate sends itself
+//! rather than delegating to `raystack::SkySparkClient` (which only
+//! exposes a fixed menu of ops). Nothing here is public API; callers
+//! go through [`crate::SkySparkClient`](crate::SkySparkClient).
+
+use raystack::{Error, Grid};
+use serde_json::Value;
+use url::Url;
+
+/// Builds the URL for a Haystack op relative to a project API url.
+pub(crate) fn op_url(project_api_url: &Url, op_name: &str) -> Url {
+    project_api_url
+        .join(op_name)
+        .unwrap_or_else(|err| panic!("'{}' is not a valid Haystack op name: {}", op_name, err))
+}
+
+/// The `Authorization` header value SkySpark expects once a SCRAM
+/// auth token has been negotiated.
+pub(crate) fn auth_header(auth_token: &str) -> String {
+    format!("BEARER authToken={}", auth_token)
+}
+
+/// Builds a Hayson grid (as a JSON value) from a meta dict and a list
+/// of already-Hayson-encoded rows. Columns are derived from the union
+/// of the row keys.
+pub(crate) fn build_grid_json(meta: Value, rows: Vec<Value>) -> Value {
+    let mut col_names = std::collections::BTreeSet::new();
+    for row in &rows {
+        if let Value::Object(map) = row {
+            col_names.extend(map.keys().cloned());
+        }
+    }
+    let cols: Vec<Value> = col_names
+        .into_iter()
+        .map(|name| serde_json::json!({ "name": name }))
+        .collect();
+    serde_json::json!({
+        "_kind": "grid",
+        "meta": meta,
+        "cols": cols,
+        "rows": rows,
+    })
+}
+
+/// An error from a request this crate builds and sends itself (as
+/// opposed to one delegated to `raystack::SkySparkClient`, which
+/// returns a bare `raystack::Error`). Distinguishes an expired/missing
+/// auth token from every other failure by the response's actual HTTP
+/// status code, so callers like
+/// [`SkySparkClient::run_with_retry`](crate::SkySparkClient) don't
+/// have to infer it later from an opaque error's rendered message.
+#[derive(Debug)]
+pub(crate) enum RequestError {
+    Unauthorized(Error),
+    Other(Error),
+}
+
+impl RequestError {
+    /// Discards the structural auth/non-auth distinction and returns
+    /// the underlying `raystack::Error`, for callers that don't retry
+    /// (and so just need the public `Result<T, Error>` shape).
+    pub(crate) fn into_inner(self) -> Error {
+        match self {
+            RequestError::Unauthorized(err) | RequestError::Other(err) => err,
+        }
+    }
+}
+
+/// Sends a built `reqwest::RequestBuilder`, checking the response's
+/// status code structurally (before it's folded into an opaque
+/// `raystack::Error`) so a 401 can be told apart from every other
+/// failure.
+async fn send_and_parse_grid(
+    request_builder: reqwest::RequestBuilder,
+) -> Result<Grid, RequestError> {
+    let response = request_builder
+        .send()
+        .await
+        .map_err(|err| RequestError::Other(Error::from(err)))?;
+    let status = response.status();
+    match response.error_for_status() {
+        Ok(response) => {
+            let text = response
+                .text()
+                .await
+                .map_err(|err| RequestError::Other(Error::from(err)))?;
+            Grid::from_json_string(&text)
+                .map_err(Error::from)
+                .map_err(RequestError::Other)
+        }
+        Err(err) => {
+            let err = Error::from(err);
+            if status == reqwest::StatusCode::UNAUTHORIZED {
+                Err(RequestError::Unauthorized(err))
+            } else {
+                Err(RequestError::Other(err))
+            }
+        }
+    }
+}
+
+/// POSTs a Hayson-encoded grid to `{project_api_url}/{op_name}` using
+/// the given auth token, and returns the parsed response grid.
+pub(crate) async fn post_grid(
+    http_client: &reqwest::Client,
+    project_api_url: &Url,
+    auth_token: &str,
+    op_name: &str,
+    request: &Value,
+) -> Result<Grid, RequestError> {
+    let url = op_url(project_api_url, op_name);
+    let request_builder = http_client
+        .post(url)
+        .header(reqwest::header::AUTHORIZATION, auth_header(auth_token))
+        .header(reqwest::header::ACCEPT, "application/json")
+        .json(request);
+    send_and_parse_grid(request_builder).await
+}
+
+/// GETs `{project_api_url}/{op_name}` using the given auth token, and
+/// returns the parsed response grid.
+pub(crate) async fn get_grid(
+    http_client: &reqwest::Client,
+    project_api_url: &Url,
+    auth_token: &str,
+    op_name: &str,
+) -> Result<Grid, RequestError> {
+    let url = op_url(project_api_url, op_name);
+    let request_builder = http_client
+        .get(url)
+        .header(reqwest::header::AUTHORIZATION, auth_header(auth_token))
+        .header(reqwest::header::ACCEPT, "application/json");
+    send_and_parse_grid(request_builder).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auth_header_uses_skyspark_bearer_scram_scheme() {
+        assert_eq!(auth_header("tokABC123"), "BEARER authToken=tokABC123");
+    }
+
+    #[test]
+    fn op_url_joins_op_name_onto_project_api_url() {
+        let base = Url::parse("https://skyspark.example.com/api/bigProject/").unwrap();
+        let url = op_url(&base, "pointWrite");
+        assert_eq!(
+            url.as_str(),
+            "https://skyspark.example.com/api/bigProject/pointWrite"
+        );
+    }
+
+    #[test]
+    fn build_grid_json_derives_cols_from_row_keys() {
+        let rows = vec![
+            serde_json::json!({"id": "r:a", "level": 8}),
+            serde_json::json!({"id": "r:b", "who": "tester"}),
+        ];
+        let grid = build_grid_json(serde_json::json!({}), rows);
+        let col_names: Vec<&str> = grid["cols"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(col_names, vec!["id", "level", "who"]);
+        assert_eq!(grid["rows"].as_array().unwrap().len(), 2);
+    }
+}
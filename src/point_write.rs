@@ -0,0 +1,74 @@
+//! Pure request-building helpers for `pointWrite`, factored out of
+//! [`crate::SkySparkClient`] so the row shape can be unit tested
+//! without a server. `id`/`val`/`dur` are passed in already
+//! Hayson-encoded, since that encoding is owned by `raystack`'s
+//! `Hayson` trait.
+
+use serde_json::{json, Map, Value};
+
+/// Builds a single `pointWrite` request row. To auto a level of the
+/// priority array instead of writing to it, callers pass `val` as
+/// Hayson's `Remove` marker (`{"_kind":"remove"}`, from
+/// `raystack::RemoveMarker::to_hayson`) rather than JSON `null` --
+/// SkySpark's `pointWrite` op reads `val` as a Hayson value, and a
+/// bare JSON `null` isn't one.
+pub(crate) fn write_row(
+    id: Value,
+    level: u8,
+    val: Value,
+    who: Option<&str>,
+    duration: Option<Value>,
+) -> Value {
+    let mut row = Map::new();
+    row.insert("id".to_string(), id);
+    row.insert("level".to_string(), json!(level));
+    row.insert("val".to_string(), val);
+    if let Some(who) = who {
+        row.insert("who".to_string(), json!(who));
+    }
+    if let Some(duration) = duration {
+        row.insert("dur".to_string(), duration);
+    }
+    Value::Object(row)
+}
+
+/// Builds the request row for reading a point's full priority array.
+pub(crate) fn read_row(id: Value) -> Value {
+    json!({ "id": id })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_row_omits_who_and_duration_when_absent() {
+        let remove = json!({"_kind": "remove"});
+        let row = write_row(json!("r:demo"), 8, remove.clone(), None, None);
+        assert_eq!(row["id"], json!("r:demo"));
+        assert_eq!(row["level"], json!(8));
+        assert_eq!(row["val"], remove);
+        assert!(row.as_object().unwrap().get("who").is_none());
+        assert!(row.as_object().unwrap().get("dur").is_none());
+    }
+
+    #[test]
+    fn write_row_includes_who_and_duration_when_given() {
+        let row = write_row(
+            json!("r:demo"),
+            1,
+            json!(true),
+            Some("tester"),
+            Some(json!("30min")),
+        );
+        assert_eq!(row["val"], json!(true));
+        assert_eq!(row["who"], json!("tester"));
+        assert_eq!(row["dur"], json!("30min"));
+    }
+
+    #[test]
+    fn read_row_contains_only_id() {
+        let row = read_row(json!("r:demo"));
+        assert_eq!(row, json!({ "id": "r:demo" }));
+    }
+}
@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+/// Controls whether, and how, a
+/// [`SkySparkClient`](crate::SkySparkClient) automatically
+/// re-authenticates and retries a request after its SkySpark auth
+/// token has expired.
+///
+/// The default, [`RetryPolicy::none`], never retries, matching the
+/// crate's previous behaviour.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub(crate) max_attempts: u32,
+    pub(crate) backoff: Backoff,
+}
+
+impl RetryPolicy {
+    /// Never retries on an expired auth token.
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_attempts: 0,
+            backoff: Backoff::Fixed(Duration::from_secs(0)),
+        }
+    }
+
+    /// Re-authenticates and retries the request up to `max_attempts`
+    /// times, waiting according to `backoff` between each attempt.
+    pub fn new(max_attempts: u32, backoff: Backoff) -> Self {
+        RetryPolicy {
+            max_attempts,
+            backoff,
+        }
+    }
+
+    pub(crate) fn delay_for_attempt(&self, attempt: u32) -> Duration {
+        match self.backoff {
+            Backoff::Fixed(duration) => duration,
+            Backoff::Exponential {
+                initial,
+                multiplier,
+                max,
+            } => {
+                let scale = multiplier.powi(attempt as i32 - 1);
+                let scaled = initial.mul_f64(scale.max(0.0));
+                scaled.min(max)
+            }
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::none()
+    }
+}
+
+/// The delay applied between a `SkySparkClient`'s retry attempts.
+#[derive(Debug, Clone, Copy)]
+pub enum Backoff {
+    /// Wait the same duration before every retry attempt.
+    Fixed(Duration),
+    /// Wait `initial * multiplier.powi(attempt - 1)` before each
+    /// attempt, capped at `max`.
+    Exponential {
+        initial: Duration,
+        multiplier: f64,
+        max: Duration,
+    },
+}
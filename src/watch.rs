@@ -0,0 +1,215 @@
+use crate::haystack_http::{build_grid_json, post_grid};
+use crate::Result;
+use raystack::{Grid, Number};
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+use url::Url;
+
+/// Builds the meta dict for an initial `watchSub` request.
+pub(crate) fn sub_request_meta(display_name: &str, lease: Option<Value>) -> Value {
+    let mut meta = serde_json::Map::new();
+    meta.insert(
+        "watchDis".to_string(),
+        Value::String(display_name.to_string()),
+    );
+    if let Some(lease) = lease {
+        meta.insert("lease".to_string(), lease);
+    }
+    Value::Object(meta)
+}
+
+/// Builds the meta dict for a `watchSub` request which grows or
+/// shrinks an existing watch (carries the existing `watchId` instead
+/// of a display name).
+pub(crate) fn resub_request_meta(watch_id: &str) -> Value {
+    serde_json::json!({ "watchId": watch_id })
+}
+
+/// Builds the row for a single watched id.
+pub(crate) fn id_row(id: Value) -> Value {
+    serde_json::json!({ "id": id })
+}
+
+/// Builds the meta dict for a `watchPoll` request.
+pub(crate) fn poll_request_meta(watch_id: &str, cur_vals_only: bool) -> Value {
+    serde_json::json!({ "watchId": watch_id, "curValsOnly": cur_vals_only })
+}
+
+/// Builds the meta dict for a `watchUnsub` call. With `close: true`
+/// the whole watch is closed regardless of which ids are in the
+/// request; with `close: false` only the ids in the request are
+/// removed from the watch, which otherwise stays open.
+pub(crate) fn unsub_request_meta(watch_id: &str, close: bool) -> Value {
+    if close {
+        serde_json::json!({ "watchId": watch_id, "close": { "_kind": "marker" } })
+    } else {
+        serde_json::json!({ "watchId": watch_id })
+    }
+}
+
+/// Reads the `watchId` and negotiated `lease` back out of a
+/// `watchSub` response grid's meta.
+pub(crate) fn parse_sub_response(meta: &Value) -> (Option<String>, Option<Value>) {
+    let watch_id = meta
+        .pointer("/watchId")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let lease = meta.pointer("/lease").cloned();
+    (watch_id, lease)
+}
+
+/// A subscription to live changes for a set of records, created by
+/// [`SkySparkClient::watch_sub`](crate::SkySparkClient::watch_sub).
+///
+/// The underlying SkySpark watch has a lease, reported by
+/// [`Watch::lease`], and is cancelled by the server if it isn't
+/// polled again before the lease expires. Callers should use the
+/// lease to schedule calls to [`Watch::poll`].
+///
+/// A `Watch` doesn't participate in the owning
+/// [`SkySparkClient`](crate::SkySparkClient)'s automatic
+/// re-authentication: if its auth token has expired, re-establish the
+/// subscription with `SkySparkClient::watch_sub` instead of retrying
+/// calls on a stale `Watch`.
+#[derive(Debug)]
+pub struct Watch {
+    http_client: reqwest::Client,
+    rt: Arc<Runtime>,
+    project_api_url: Url,
+    auth_token: String,
+    watch_id: String,
+    lease: Option<Number>,
+}
+
+impl Watch {
+    pub(crate) fn new(
+        http_client: reqwest::Client,
+        rt: Arc<Runtime>,
+        project_api_url: Url,
+        auth_token: String,
+        watch_id: String,
+        lease: Option<Number>,
+    ) -> Self {
+        Self {
+            http_client,
+            rt,
+            project_api_url,
+            auth_token,
+            watch_id,
+            lease,
+        }
+    }
+
+    /// Returns the watch id assigned by the server.
+    pub fn id(&self) -> &str {
+        &self.watch_id
+    }
+
+    /// Returns the lease duration negotiated with the server.
+    pub fn lease(&self) -> Option<Number> {
+        self.lease.clone()
+    }
+
+    fn post(&self, op_name: &str, request: &Value) -> Result<Grid> {
+        self.rt
+            .block_on(post_grid(
+                &self.http_client,
+                &self.project_api_url,
+                &self.auth_token,
+                op_name,
+                request,
+            ))
+            .map_err(|err| err.into_inner())
+    }
+
+    /// Polls the server for any records which have changed since the
+    /// last poll.
+    pub fn poll(&mut self) -> Result<Grid> {
+        let request = build_grid_json(poll_request_meta(&self.watch_id, false), Vec::new());
+        self.post("watchPoll", &request)
+    }
+
+    /// Polls the server, forcing it to return the current value of
+    /// every watched record instead of only the ones that changed.
+    pub fn poll_refresh(&mut self) -> Result<Grid> {
+        let request = build_grid_json(poll_request_meta(&self.watch_id, true), Vec::new());
+        self.post("watchPoll", &request)
+    }
+
+    /// Adds the given ids to this watch by re-issuing `watchSub` with
+    /// the existing `watchId`.
+    pub fn add(&mut self, ids: &[raystack::Ref]) -> Result<Grid> {
+        let meta = resub_request_meta(&self.watch_id);
+        let rows = ids.iter().map(|id| id_row(id.to_hayson())).collect();
+        let request = build_grid_json(meta, rows);
+        self.post("watchSub", &request)
+    }
+
+    /// Removes the given ids from this watch via `watchUnsub`. Unlike
+    /// [`Watch::add`], this can't be done by re-issuing `watchSub`
+    /// with the existing `watchId`: that only ever grows a watch, it
+    /// never shrinks one.
+    pub fn remove(&mut self, ids: &[raystack::Ref]) -> Result<Grid> {
+        let meta = unsub_request_meta(&self.watch_id, false);
+        let rows = ids.iter().map(|id| id_row(id.to_hayson())).collect();
+        let request = build_grid_json(meta, rows);
+        self.post("watchUnsub", &request)
+    }
+
+    /// Closes the watch on the server. The `Watch` should not be used
+    /// again after calling this.
+    pub fn close(self) -> Result<()> {
+        let request = build_grid_json(unsub_request_meta(&self.watch_id, true), Vec::new());
+        self.post("watchUnsub", &request)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sub_request_meta_includes_display_name_and_lease() {
+        let meta = sub_request_meta("My Watch", Some(Value::String("5min".to_string())));
+        assert_eq!(meta["watchDis"], Value::String("My Watch".to_string()));
+        assert_eq!(meta["lease"], Value::String("5min".to_string()));
+    }
+
+    #[test]
+    fn sub_request_meta_omits_lease_when_absent() {
+        let meta = sub_request_meta("My Watch", None);
+        assert!(meta.as_object().unwrap().get("lease").is_none());
+    }
+
+    #[test]
+    fn unsub_request_meta_carries_close_marker_when_closing() {
+        let meta = unsub_request_meta("w-123", true);
+        assert_eq!(meta["watchId"], Value::String("w-123".to_string()));
+        assert_eq!(meta["close"]["_kind"], Value::String("marker".to_string()));
+    }
+
+    #[test]
+    fn unsub_request_meta_omits_close_marker_when_only_removing_ids() {
+        let meta = unsub_request_meta("w-123", false);
+        assert_eq!(meta["watchId"], Value::String("w-123".to_string()));
+        assert!(meta.as_object().unwrap().get("close").is_none());
+    }
+
+    #[test]
+    fn parse_sub_response_reads_watch_id_and_lease() {
+        let meta = serde_json::json!({"watchId": "w-123", "lease": "5min"});
+        let (watch_id, lease) = parse_sub_response(&meta);
+        assert_eq!(watch_id, Some("w-123".to_string()));
+        assert_eq!(lease, Some(Value::String("5min".to_string())));
+    }
+
+    #[test]
+    fn parse_sub_response_handles_missing_fields() {
+        let meta = serde_json::json!({});
+        let (watch_id, lease) = parse_sub_response(&meta);
+        assert_eq!(watch_id, None);
+        assert_eq!(lease, None);
+    }
+}